@@ -1,25 +1,139 @@
 use crate::manager::{DeviceLocation, DeviceType, PID_BEACN_MIC, PID_BEACN_STUDIO, VENDOR_BEACN};
 use crate::messages::{DeviceMessageType, Message};
+use crate::transport::{BeacnTransport, RusbTransport};
 use crate::version::VersionNumber;
-use anyhow::{Result, bail};
+use anyhow::{Result, anyhow, bail};
 use byteorder::{LittleEndian, ReadBytesExt};
 use log::{debug, warn};
-use rusb::{Device, DeviceDescriptor, DeviceHandle, GlobalContext};
+use rusb::{Device, DeviceDescriptor, GlobalContext};
+use std::collections::HashMap;
 use std::io::{Cursor, Read, Seek};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
-pub struct BeacnMic {
+/// Consecutive read timeouts on `0x83` a [`BeacnMic::subscribe`] reader will
+/// tolerate before assuming the device doesn't push and falling back to
+/// polling (roughly 2s at the reader's 100ms read timeout).
+const PUSH_IDLE_THRESHOLD: u32 = 20;
+
+/// Distinguishes a genuine read timeout (nothing pushed yet, keep polling)
+/// from a fatal transport error (device unplugged, endpoint gone, etc).
+fn is_timeout(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref::<rusb::Error>(), Some(rusb::Error::Timeout))
+}
+
+/// Decodes a frame read off `0x83` into a [`Message`], guarding against the
+/// fact that `Message::from_beacn_message` can panic on a key it doesn't
+/// recognise (see e.g. `Exciter::from_beacn`). An unsolicited push frame is
+/// exactly the kind of input that can legitimately carry an unknown key, so
+/// a decode failure here is logged and discarded rather than taking down
+/// the reader thread.
+pub(crate) fn decode_push_frame(buf: [u8; 8], device_type: DeviceType) -> Option<Message> {
+    match std::panic::catch_unwind(|| Message::from_beacn_message(buf, device_type)) {
+        Ok(message) => Some(message),
+        Err(_) => {
+            warn!("Discarding unrecognized frame from device: {:?}", buf);
+            None
+        }
+    }
+}
+
+/// Issues a `0xa3` lookup request for every parameter `generate_fetch_message`
+/// knows about, for devices that never push state changes on their own.
+/// Returns the number of solicited `0xa4` replies the caller should now
+/// expect back on `0x83`.
+fn poll_all_parameters<T: BeacnTransport>(
+    transport: &T,
+    device_type: DeviceType,
+    timeout: Duration,
+) -> usize {
+    let mut issued = 0;
+    for request in Message::generate_fetch_message(device_type) {
+        let key = request.to_beacn_key();
+
+        let mut frame = [0; 4];
+        frame[0..3].copy_from_slice(&key);
+        frame[3] = 0xa3;
+
+        match transport.write(0x03, &frame, timeout) {
+            Ok(_) => issued += 1,
+            Err(err) => {
+                warn!("Failed to issue subscription poll for {:?}: {}", request, err);
+                break;
+            }
+        }
+    }
+    issued
+}
+
+pub struct BeacnMic<T: BeacnTransport = RusbTransport> {
     device_type: DeviceType,
 
-    handle: DeviceHandle<GlobalContext>,
-    device: Device<GlobalContext>,
-    _descriptor: DeviceDescriptor,
+    transport: Arc<T>,
+
+    /// Endpoints `0x03`/`0x83` are shared between every synchronous call
+    /// (`param_lookup`/`param_set`/`fetch_values`) and the background reader
+    /// spawned by [`BeacnMic::subscribe`]. A `read()` physically consumes
+    /// whatever frame is next in the endpoint's queue, so without this lock a
+    /// reply meant for a synchronous caller can be stolen (and discarded) by
+    /// the subscribe thread before the caller ever sees it. Every method that
+    /// talks to the device holds this for the duration of its request/reply
+    /// round-trip; the subscribe thread only holds it for a single read (or
+    /// one round of poll writes), and releases it between iterations.
+    io_lock: Arc<Mutex<()>>,
 
     serial: String,
     firmware_version: VersionNumber,
 }
 
-impl BeacnMic {
+/// A live stream of decoded [`Message`]s read from a [`BeacnMic`], created by
+/// [`BeacnMic::subscribe`].
+///
+/// The background reader thread is stopped either explicitly via
+/// [`Subscription::unsubscribe`] or implicitly when the `Subscription` is
+/// dropped.
+pub struct Subscription {
+    receiver: Receiver<Message>,
+    stop: Arc<AtomicBool>,
+    reader: Option<JoinHandle<()>>,
+}
+
+impl Subscription {
+    /// Blocks until the next decoded message arrives, or the subscription is
+    /// torn down.
+    pub fn recv(&self) -> Result<Message> {
+        Ok(self.receiver.recv()?)
+    }
+
+    /// Returns an iterator over messages as they arrive, ending once the
+    /// subscription is stopped.
+    pub fn iter(&self) -> impl Iterator<Item = Message> + '_ {
+        self.receiver.iter()
+    }
+
+    /// Stops the background reader thread and releases the endpoint.
+    pub fn unsubscribe(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl BeacnMic<RusbTransport> {
     pub fn open(location: DeviceLocation) -> Result<Self> {
         // Attempt to Locate a Beacn Mic
         let (device, descriptor) = Self::find_device(location)?;
@@ -33,19 +147,55 @@ impl BeacnMic {
         };
 
         let handle = device.open()?;
-        handle.claim_interface(3)?;
-        handle.set_alternate_setting(3, 1)?;
-        handle.clear_halt(0x83)?;
+        let transport = RusbTransport::new(handle, device, descriptor);
+
+        Self::from_transport(transport, device_type)
+    }
+
+    pub fn get_location(&self) -> String {
+        format!(
+            "{}.{}",
+            self.transport.device().bus_number(),
+            self.transport.device().address()
+        )
+    }
+
+    #[allow(clippy::collapsible_if)]
+    fn find_device(location: DeviceLocation) -> Result<(Device<GlobalContext>, DeviceDescriptor)> {
+        // We need to iterate through the devices and find the one at this location
+        if let Ok(devices) = rusb::devices() {
+            for device in devices.iter() {
+                if let Ok(desc) = device.device_descriptor() {
+                    if desc.vendor_id() == VENDOR_BEACN {
+                        if DeviceLocation::from(device.clone()) == location {
+                            return Ok((device, desc));
+                        }
+                    }
+                }
+            }
+        }
+        bail!("Unable to find Device")
+    }
+}
+
+impl<T: BeacnTransport> BeacnMic<T> {
+    /// Builds a `BeacnMic` on top of an already-constructed transport,
+    /// running the same handshake that `open` performs for real hardware.
+    /// This is the entry point for tests and alternate backends that supply
+    /// their own [`BeacnTransport`] (e.g. `MockTransport`), which have no
+    /// USB descriptor to sniff the device type from.
+    pub fn from_transport(transport: T, device_type: DeviceType) -> Result<Self> {
+        transport.claim()?;
 
         let setup_timeout = Duration::from_millis(2000);
 
         let request = [0x00, 0x00, 0x00, 0xa0];
-        handle.write_bulk(0x03, &request, setup_timeout)?;
+        transport.write(0x03, &request, setup_timeout)?;
 
         let mut input = [0; 512];
         let request = [0x00, 0x00, 0x00, 0xa1];
-        handle.write_bulk(0x03, &request, setup_timeout)?;
-        handle.read_bulk(0x83, &mut input, setup_timeout)?;
+        transport.write(0x03, &request, setup_timeout)?;
+        transport.read(0x83, &mut input, setup_timeout)?;
 
         // So, this is consistent between the Mix Create and the Mic :D
         let mut cursor = Cursor::new(input);
@@ -75,9 +225,7 @@ impl BeacnMic {
         let serial = String::from_utf8_lossy(&serial_bytes).to_string();
 
         debug!(
-            "Loaded Device, Location: {}.{}, Serial: {}, Version: {}",
-            device.bus_number(),
-            device.address(),
+            "Loaded Device, Serial: {}, Version: {}",
             serial.clone(),
             firmware_version
         );
@@ -85,9 +233,8 @@ impl BeacnMic {
         Ok(Self {
             device_type,
 
-            handle,
-            device,
-            _descriptor: descriptor,
+            transport: Arc::new(transport),
+            io_lock: Arc::new(Mutex::new(())),
 
             serial,
             firmware_version,
@@ -102,8 +249,99 @@ impl BeacnMic {
         self.firmware_version
     }
 
-    pub fn get_location(&self) -> String {
-        format!("{}.{}", self.device.bus_number(), self.device.address())
+    /// Spawns a background thread that continuously polls endpoint `0x83`
+    /// and decodes pushed frames, delivering them through the returned
+    /// [`Subscription`] as they arrive. This gives callers a uniform stream
+    /// of updates for live level meters or syncing UI state, without each
+    /// caller having to drive its own `fetch_value` polling loop.
+    ///
+    /// Every frame's header byte (`buf[3]`) is checked against the `0xa4`
+    /// marker `param_lookup`/`param_set` use for solicited replies: a
+    /// `0xa4` frame is only ours to forward while we're actively draining a
+    /// poll we issued ourselves (see below), otherwise it's a reply to a
+    /// concurrent `fetch_value`/`set_value` call and must be left alone.
+    /// Anything else is an unsolicited push frame from the device.
+    ///
+    /// That classification alone isn't enough to protect a concurrent
+    /// caller, though: by the time this thread can inspect `buf[3]` it has
+    /// already consumed the frame off `0x83`, so a `fetch_value` waiting on
+    /// that exact reply would otherwise time out with nothing to read. The
+    /// reader thread therefore takes the same `io_lock` every synchronous
+    /// call holds for its whole round-trip, but only around a single read
+    /// (or one round of poll writes) at a time, releasing it between
+    /// iterations.
+    ///
+    /// If the device stays silent for [`PUSH_IDLE_THRESHOLD`] ticks, the
+    /// thread falls back to actively polling every parameter via
+    /// `generate_fetch_message`, so devices that never push still produce a
+    /// steady stream of updates.
+    pub fn subscribe(&self) -> Subscription
+    where
+        T: Send + Sync + 'static,
+    {
+        let transport = Arc::clone(&self.transport);
+        let io_lock = Arc::clone(&self.io_lock);
+        let device_type = self.device_type;
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+
+        let thread_stop = Arc::clone(&stop);
+        let reader = thread::spawn(move || {
+            let read_timeout = Duration::from_millis(100);
+            let write_timeout = Duration::from_millis(200);
+
+            let mut idle_ticks = 0u32;
+            let mut awaiting_poll_replies = 0usize;
+            let mut buf = [0; 8];
+
+            while !thread_stop.load(Ordering::Relaxed) {
+                let read_result = {
+                    let _guard = io_lock.lock().unwrap();
+                    transport.read(0x83, &mut buf, read_timeout)
+                };
+
+                match read_result {
+                    Ok(_) => {
+                        idle_ticks = 0;
+
+                        let is_solicited_reply = buf[3] == 0xa4;
+                        if is_solicited_reply {
+                            if awaiting_poll_replies == 0 {
+                                // Belongs to someone else's fetch_value/set_value.
+                                continue;
+                            }
+                            awaiting_poll_replies -= 1;
+                        }
+
+                        if let Some(message) = decode_push_frame(buf, device_type) {
+                            if sender.send(message).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(err) if is_timeout(&err) => {
+                        idle_ticks += 1;
+                        if idle_ticks >= PUSH_IDLE_THRESHOLD && awaiting_poll_replies == 0 {
+                            let _guard = io_lock.lock().unwrap();
+                            awaiting_poll_replies =
+                                poll_all_parameters(&transport, device_type, write_timeout);
+                            idle_ticks = 0;
+                        }
+                    }
+                    Err(_) => {
+                        // Anything other than a timeout (device unplugged,
+                        // endpoint gone, etc) is fatal for this subscription.
+                        break;
+                    }
+                }
+            }
+        });
+
+        Subscription {
+            receiver,
+            stop,
+            reader: Some(reader),
+        }
     }
 
     pub fn is_command_valid(&self, message: Message) -> bool {
@@ -132,6 +370,108 @@ impl BeacnMic {
         Ok(Message::from_beacn_message(param, self.device_type))
     }
 
+    /// Fetches several parameters in one round-trip instead of paying a
+    /// full write-then-read per key: every `0xa3` lookup request is written
+    /// back-to-back, then the matching `0xa4` responses are drained off
+    /// `0x83` and matched back to their request by key, since the device
+    /// isn't guaranteed to answer in the order it was asked. Results are
+    /// returned in input order, with a per-item error for messages that
+    /// aren't valid for this device or that the device never answered.
+    ///
+    /// Replies are matched on the full 3-byte key (`buf[0..3]`), unlike
+    /// `param_lookup`'s 2-byte check: a single-shot lookup only ever has one
+    /// request in flight, so the third (category/index) byte can't collide,
+    /// but a batch routes many interleaved replies at once and `snapshot`
+    /// batches every parameter across every category — exactly where that
+    /// third byte is the discriminator between otherwise-identical keys.
+    pub fn fetch_values(&self, messages: &[Message]) -> Result<Vec<Result<Message>>> {
+        if messages.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Held for the whole batch so `subscribe`'s reader thread can't steal
+        // one of our replies between the writes below and the drain loop.
+        let _guard = self.io_lock.lock().unwrap();
+
+        let timeout = Duration::from_secs(3);
+        let mut keys = Vec::with_capacity(messages.len());
+
+        for message in messages {
+            if !self.is_command_valid(*message) {
+                warn!("Command Sent not valid for this device:");
+                warn!("{:?}", message);
+                keys.push(None);
+                continue;
+            }
+
+            let key = message.to_beacn_key();
+
+            let mut request = [0; 4];
+            request[0..3].copy_from_slice(&key);
+            request[3] = 0xa3;
+            self.transport.write(0x03, &request, timeout)?;
+
+            keys.push(Some(key));
+        }
+
+        // A key asked for more than once maps to every index that asked for it.
+        let mut pending: HashMap<[u8; 3], Vec<usize>> = HashMap::new();
+        for (index, key) in keys.iter().enumerate() {
+            if let Some(key) = key {
+                pending.entry(*key).or_default().push(index);
+            }
+        }
+
+        let mut results: Vec<Option<Result<Message>>> = (0..messages.len()).map(|_| None).collect();
+
+        // Bounded by a deadline rather than a fixed number of read attempts:
+        // a stale, duplicate, or unsolicited frame shouldn't consume an
+        // attempt that a genuinely-pending reply further back in the stream
+        // still needs.
+        let deadline = std::time::Instant::now() + timeout;
+        let drain_timeout = Duration::from_millis(250);
+
+        while !pending.is_empty() && std::time::Instant::now() < deadline {
+            let mut buf = [0; 8];
+            match self.transport.read(0x83, &mut buf, drain_timeout) {
+                Ok(_) => {
+                    let key: [u8; 3] = buf[0..3].try_into().unwrap();
+                    if let Some(indices) = pending.remove(&key) {
+                        let decoded = decode_push_frame(buf, self.device_type);
+                        for index in indices {
+                            results[index] = Some(
+                                decoded.ok_or_else(|| anyhow!("Unrecognized reply for key {:?}", key)),
+                            );
+                        }
+                    }
+                    // Otherwise this frame belongs to an already-answered,
+                    // duplicate, or unrelated request; ignore and keep
+                    // draining without spending one of the pending keys.
+                }
+                Err(err) if is_timeout(&err) => continue,
+                Err(err) => {
+                    warn!("Error waiting for batch fetch response: {}", err);
+                    break;
+                }
+            }
+        }
+
+        for (key, indices) in pending {
+            warn!("Device never answered batch fetch key {:?}", key);
+            for index in indices {
+                results[index] = Some(Err(anyhow!("Device did not respond to key {:?}", key)));
+            }
+        }
+
+        Ok(results
+            .into_iter()
+            .zip(messages)
+            .map(|(result, message)| {
+                result.unwrap_or_else(|| Err(anyhow!("Command is not valid for this device: {:?}", message)))
+            })
+            .collect())
+    }
+
     pub fn set_value(&self, message: Message) -> Result<Message> {
         if !self.is_command_valid(message) {
             warn!("Command Sent not valid for this device:");
@@ -149,7 +489,16 @@ impl BeacnMic {
         Ok(Message::from_beacn_message(result, self.device_type))
     }
 
+    /// Takes `io_lock` for the duration of the write/read round-trip, then
+    /// delegates to [`Self::raw_param_lookup`]. Callers that already hold the
+    /// lock (e.g. `param_set`) must call `raw_param_lookup` directly instead,
+    /// or they'll deadlock on a `Mutex` that isn't reentrant.
     fn param_lookup(&self, key: [u8; 3]) -> Result<[u8; 8]> {
+        let _guard = self.io_lock.lock().unwrap();
+        self.raw_param_lookup(key)
+    }
+
+    fn raw_param_lookup(&self, key: [u8; 3]) -> Result<[u8; 8]> {
         let timeout = Duration::from_secs(3);
 
         let mut request = [0; 4];
@@ -157,11 +506,11 @@ impl BeacnMic {
         request[3] = 0xa3;
 
         // Write out the command request
-        self.handle.write_bulk(0x03, &request, timeout)?;
+        self.transport.write(0x03, &request, timeout)?;
 
         // Grab the response into a buffer
         let mut buf = [0; 8];
-        self.handle.read_bulk(0x83, &mut buf, timeout)?;
+        self.transport.read(0x83, &mut buf, timeout)?;
 
         // Validate the header...
         if buf[0..2] != request[0..2] || buf[3] != 0xa4 {
@@ -172,6 +521,8 @@ impl BeacnMic {
     }
 
     fn param_set(&self, key: [u8; 3], value: [u8; 4]) -> Result<[u8; 8]> {
+        let _guard = self.io_lock.lock().unwrap();
+
         let timeout = Duration::from_millis(200);
 
         // Build the Set Request
@@ -181,10 +532,11 @@ impl BeacnMic {
         request[4..].copy_from_slice(&value);
 
         // Write out the command request
-        self.handle.write_bulk(0x03, &request, timeout)?;
+        self.transport.write(0x03, &request, timeout)?;
 
-        // Check whether the value has changed
-        let new_value = self.param_lookup(key)?;
+        // Check whether the value has changed. Use the non-locking lookup
+        // since we already hold `io_lock`.
+        let new_value = self.raw_param_lookup(key)?;
 
         let old = &request[4..8];
         let new = &new_value[4..8];
@@ -199,21 +551,4 @@ impl BeacnMic {
         }
         Ok(new_value)
     }
-
-    #[allow(clippy::collapsible_if)]
-    fn find_device(location: DeviceLocation) -> Result<(Device<GlobalContext>, DeviceDescriptor)> {
-        // We need to iterate through the devices and find the one at this location
-        if let Ok(devices) = rusb::devices() {
-            for device in devices.iter() {
-                if let Ok(desc) = device.device_descriptor() {
-                    if desc.vendor_id() == VENDOR_BEACN {
-                        if DeviceLocation::from(device.clone()) == location {
-                            return Ok((device, desc));
-                        }
-                    }
-                }
-            }
-        }
-        bail!("Unable to find Device")
-    }
 }
@@ -0,0 +1,195 @@
+use crate::device::BeacnMic;
+use anyhow::{Context, Result, bail};
+use log::{debug, error, warn};
+use rusb::{Device, GlobalContext, Hotplug, HotplugBuilder, Registration, UsbContext};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+pub const VENDOR_BEACN: u16 = 0x33ae;
+pub const PID_BEACN_MIC: u16 = 0x0001;
+pub const PID_BEACN_STUDIO: u16 = 0x0002;
+
+const MAX_CLAIM_ATTEMPTS: u32 = 5;
+
+// `Serialize`/`Deserialize` are needed so `DeviceType` can be tagged onto a
+// `DeviceSnapshot` (see `snapshot.rs`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeviceType {
+    BeacnMic,
+    BeacnStudio,
+}
+
+/// Identifies a physical USB port a device is plugged into, independent of
+/// any higher level state. Used as the key for [`BeacnManager`]'s live
+/// device map, since it stays stable across a single plug-in session.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DeviceLocation {
+    pub bus_number: u8,
+    pub address: u8,
+}
+
+impl From<Device<GlobalContext>> for DeviceLocation {
+    fn from(device: Device<GlobalContext>) -> Self {
+        Self {
+            bus_number: device.bus_number(),
+            address: device.address(),
+        }
+    }
+}
+
+/// A device arriving or leaving, surfaced by [`BeacnManager`] to callers who
+/// want to react to a mic being plugged in or yanked mid-session.
+pub enum DeviceEvent {
+    Arrived(DeviceLocation, Arc<BeacnMic>),
+    Left(DeviceLocation),
+}
+
+struct HotplugHandler {
+    sender: Sender<DeviceEvent>,
+    devices: Arc<Mutex<HashMap<DeviceLocation, Arc<BeacnMic>>>>,
+}
+
+impl HotplugHandler {
+    /// Devices can enumerate slowly enough that claiming the interface
+    /// fails on the first attempt right after arrival; retry with backoff
+    /// before giving up on the device entirely.
+    fn open_with_retry(location: DeviceLocation) -> Option<BeacnMic> {
+        for attempt in 1..=MAX_CLAIM_ATTEMPTS {
+            match BeacnMic::open(location) {
+                Ok(mic) => return Some(mic),
+                Err(err) if attempt < MAX_CLAIM_ATTEMPTS => {
+                    warn!(
+                        "Failed to open device at {:?} (attempt {}/{}), retrying: {}",
+                        location, attempt, MAX_CLAIM_ATTEMPTS, err
+                    );
+                    thread::sleep(Duration::from_millis(100 * attempt as u64));
+                }
+                Err(err) => {
+                    error!("Giving up on device at {:?}: {}", location, err);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Hotplug<GlobalContext> for HotplugHandler {
+    fn device_arrived(&mut self, device: Device<GlobalContext>) {
+        let location = DeviceLocation::from(device);
+
+        let Some(mic) = Self::open_with_retry(location) else {
+            return;
+        };
+
+        debug!("Device arrived at {:?}", location);
+        let mic = Arc::new(mic);
+        self.devices.lock().unwrap().insert(location, Arc::clone(&mic));
+        let _ = self.sender.send(DeviceEvent::Arrived(location, mic));
+    }
+
+    fn device_left(&mut self, device: Device<GlobalContext>) {
+        let location = DeviceLocation::from(device);
+
+        debug!("Device left from {:?}", location);
+        self.devices.lock().unwrap().remove(&location);
+        let _ = self.sender.send(DeviceEvent::Left(location));
+    }
+}
+
+/// Watches for Beacn Mic / Studio devices being plugged in or removed, and
+/// keeps a live `DeviceLocation -> BeacnMic` map up to date without the
+/// caller having to poll `BeacnMic::open` themselves.
+pub struct BeacnManager {
+    devices: Arc<Mutex<HashMap<DeviceLocation, Arc<BeacnMic>>>>,
+    events: Receiver<DeviceEvent>,
+
+    _registrations: Vec<Registration<GlobalContext>>,
+
+    stop: Arc<AtomicBool>,
+    poller: Option<JoinHandle<()>>,
+}
+
+impl BeacnManager {
+    pub fn new() -> Result<Self> {
+        if !rusb::has_hotplug() {
+            bail!("This platform does not support USB hotplug detection");
+        }
+
+        let devices: Arc<Mutex<HashMap<DeviceLocation, Arc<BeacnMic>>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let (sender, events) = mpsc::channel();
+
+        let mic_registration = HotplugBuilder::new()
+            .vendor_id(VENDOR_BEACN)
+            .product_id(PID_BEACN_MIC)
+            .enumerate(true)
+            .register(
+                GlobalContext {},
+                Box::new(HotplugHandler {
+                    sender: sender.clone(),
+                    devices: Arc::clone(&devices),
+                }),
+            )
+            .context("Failed to register Mic hotplug callback")?;
+
+        let studio_registration = HotplugBuilder::new()
+            .vendor_id(VENDOR_BEACN)
+            .product_id(PID_BEACN_STUDIO)
+            .enumerate(true)
+            .register(
+                GlobalContext {},
+                Box::new(HotplugHandler {
+                    sender,
+                    devices: Arc::clone(&devices),
+                }),
+            )
+            .context("Failed to register Studio hotplug callback")?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let poller_stop = Arc::clone(&stop);
+        let poller = thread::spawn(move || {
+            while !poller_stop.load(Ordering::Relaxed) {
+                if let Err(err) = GlobalContext {}.handle_events(Some(Duration::from_millis(200)))
+                {
+                    debug!("Error polling USB hotplug events: {}", err);
+                }
+            }
+        });
+
+        Ok(Self {
+            devices,
+            events,
+
+            _registrations: vec![mic_registration, studio_registration],
+
+            stop,
+            poller: Some(poller),
+        })
+    }
+
+    /// The channel `Arrived`/`Left` events are delivered on as hotplug
+    /// activity happens.
+    pub fn events(&self) -> &Receiver<DeviceEvent> {
+        &self.events
+    }
+
+    /// A snapshot of every currently managed device, keyed by its USB
+    /// location.
+    pub fn devices(&self) -> HashMap<DeviceLocation, Arc<BeacnMic>> {
+        self.devices.lock().unwrap().clone()
+    }
+}
+
+impl Drop for BeacnManager {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(poller) = self.poller.take() {
+            let _ = poller.join();
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::device::{BeacnMic, decode_push_frame};
+use crate::manager::DeviceType;
+use crate::messages::Message;
+use crate::transport::BeacnTransport;
+use crate::version::VersionNumber;
+use anyhow::Result;
+use log::warn;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A full capture of a device's configuration (Exciter, EQ, compressor,
+/// gate, etc.) produced by [`BeacnMic::snapshot`], which can be persisted
+/// as JSON/TOML and later re-applied with [`BeacnMic::apply_snapshot`] to
+/// the same or another unit.
+///
+/// `device_type` is recorded so the stored parameters are decoded for the
+/// right device when a snapshot is loaded back; `apply_snapshot` does not
+/// compare it against the target device, it replays whichever parameters
+/// are valid there (so `Common` ones carry over between a Mic and a Studio).
+/// `firmware_version` / `serial` are kept purely as provenance for whoever
+/// is managing profiles.
+///
+/// `Message` and `VersionNumber` don't derive `Serialize`/`Deserialize`
+/// themselves, so this struct implements both manually below by going
+/// through [`DeviceSnapshotWire`], a plain-data stand-in built from the
+/// same key/value bytes the wire protocol already uses.
+#[derive(Debug, Clone)]
+pub struct DeviceSnapshot {
+    pub device_type: DeviceType,
+    pub firmware_version: VersionNumber,
+    pub serial: String,
+    pub values: Vec<Message>,
+}
+
+/// A single parameter's reply key and raw value, serialized in place of a
+/// [`Message`] (see [`DeviceSnapshotWire`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ParameterWire {
+    key: [u8; 3],
+    value: [u8; 4],
+}
+
+/// The on-disk shape of a [`DeviceSnapshot`]. `Message` and `VersionNumber`
+/// aren't serde types, so this swaps them for the plain data they're built
+/// from: a dotted version string and a `(key, value)` pair per parameter,
+/// the same bytes `BeacnMic::fetch_values`/`set_value` already exchange with
+/// the device.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DeviceSnapshotWire {
+    device_type: String,
+    firmware_version: String,
+    serial: String,
+    values: Vec<ParameterWire>,
+}
+
+impl Serialize for DeviceSnapshot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let wire = DeviceSnapshotWire {
+            device_type: format!("{:?}", self.device_type),
+            firmware_version: self.firmware_version.to_string(),
+            serial: self.serial.clone(),
+            values: self
+                .values
+                .iter()
+                .map(|message| ParameterWire {
+                    key: message.to_beacn_key(),
+                    value: message.to_beacn_value(),
+                })
+                .collect(),
+        };
+        wire.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DeviceSnapshot {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let wire = DeviceSnapshotWire::deserialize(deserializer)?;
+
+        let device_type = match wire.device_type.as_str() {
+            "BeacnMic" => DeviceType::BeacnMic,
+            "BeacnStudio" => DeviceType::BeacnStudio,
+            other => return Err(D::Error::custom(format!("Unknown device type: {}", other))),
+        };
+
+        let mut parts = wire.firmware_version.split('.');
+        let mut next_part = || -> Result<u32, D::Error> {
+            parts
+                .next()
+                .ok_or_else(|| D::Error::custom("Firmware version is missing a component"))?
+                .parse()
+                .map_err(|_| D::Error::custom("Firmware version component is not a number"))
+        };
+        let firmware_version = VersionNumber(next_part()?, next_part()?, next_part()?, next_part()?);
+        if parts.next().is_some() {
+            return Err(D::Error::custom("Firmware version has too many components"));
+        }
+
+        let values = wire
+            .values
+            .into_iter()
+            .map(|parameter| {
+                let mut buf = [0u8; 8];
+                buf[0..3].copy_from_slice(&parameter.key);
+                buf[3] = 0xa4;
+                buf[4..8].copy_from_slice(&parameter.value);
+                decode_push_frame(buf, device_type)
+                    .ok_or_else(|| D::Error::custom(format!("Unrecognized key {:?}", parameter.key)))
+            })
+            .collect::<Result<Vec<_>, D::Error>>()?;
+
+        Ok(DeviceSnapshot {
+            device_type,
+            firmware_version,
+            serial: wire.serial,
+            values,
+        })
+    }
+}
+
+/// The outcome of replaying a single parameter from a [`DeviceSnapshot`].
+#[derive(Debug)]
+pub struct ApplyOutcome {
+    pub message: Message,
+    pub result: Result<Message>,
+}
+
+impl<T: BeacnTransport> BeacnMic<T> {
+    /// Walks every parameter the protocol knows about for this device's
+    /// type, fetching them all in one batch via [`BeacnMic::fetch_values`]
+    /// and collecting the results into a portable [`DeviceSnapshot`].
+    /// Parameters the device doesn't answer are logged and skipped rather
+    /// than failing the whole snapshot.
+    pub fn snapshot(&self) -> Result<DeviceSnapshot> {
+        let requests = Message::generate_fetch_message(self.device_type);
+        let results = self.fetch_values(&requests)?;
+
+        let mut values = Vec::with_capacity(requests.len());
+        for (request, result) in requests.into_iter().zip(results) {
+            match result {
+                Ok(value) => values.push(value),
+                Err(err) => warn!("Skipping {:?} while building snapshot: {}", request, err),
+            }
+        }
+
+        Ok(DeviceSnapshot {
+            device_type: self.device_type,
+            firmware_version: self.firmware_version,
+            serial: self.serial.clone(),
+            values,
+        })
+    }
+
+    /// Replays every settable parameter from `snapshot` onto this device.
+    /// Getters and parameters not valid for this device's type are skipped;
+    /// everything else is applied through the usual `set_value` path, with
+    /// the per-parameter result reported back so a caller can surface a
+    /// partial restore instead of aborting on the first failure.
+    pub fn apply_snapshot(&self, snapshot: &DeviceSnapshot) -> Vec<ApplyOutcome> {
+        snapshot
+            .values
+            .iter()
+            .copied()
+            .filter(|message| message.is_device_message_set() && self.is_command_valid(*message))
+            .map(|message| ApplyOutcome {
+                message,
+                result: self.set_value(message),
+            })
+            .collect()
+    }
+}
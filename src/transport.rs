@@ -0,0 +1,113 @@
+use anyhow::{Result, bail};
+use rusb::{Device, DeviceDescriptor, DeviceHandle, GlobalContext};
+use std::time::Duration;
+
+/// Abstracts the USB bulk transfer calls `BeacnMic` relies on, so the protocol
+/// layer (param lookup / set, message round-tripping) can be exercised
+/// without real hardware.
+pub trait BeacnTransport {
+    /// Claims the interface and puts the device into the state the protocol
+    /// expects (alternate setting, cleared halt, etc). Called once from
+    /// `BeacnMic::from_transport` (which `BeacnMic::open` goes through).
+    fn claim(&self) -> Result<()>;
+
+    /// Writes `data` to `endpoint`, returning the number of bytes written.
+    fn write(&self, endpoint: u8, data: &[u8], timeout: Duration) -> Result<usize>;
+
+    /// Reads into `buf` from `endpoint`, returning the number of bytes read.
+    fn read(&self, endpoint: u8, buf: &mut [u8], timeout: Duration) -> Result<usize>;
+}
+
+/// The default [`BeacnTransport`] backed by `rusb`, used for all real
+/// hardware access.
+pub struct RusbTransport {
+    pub(crate) handle: DeviceHandle<GlobalContext>,
+    pub(crate) device: Device<GlobalContext>,
+    pub(crate) descriptor: DeviceDescriptor,
+}
+
+impl RusbTransport {
+    pub fn new(
+        handle: DeviceHandle<GlobalContext>,
+        device: Device<GlobalContext>,
+        descriptor: DeviceDescriptor,
+    ) -> Self {
+        Self {
+            handle,
+            device,
+            descriptor,
+        }
+    }
+
+    pub fn device(&self) -> &Device<GlobalContext> {
+        &self.device
+    }
+
+    pub fn descriptor(&self) -> &DeviceDescriptor {
+        &self.descriptor
+    }
+}
+
+impl BeacnTransport for RusbTransport {
+    fn claim(&self) -> Result<()> {
+        self.handle.claim_interface(3)?;
+        self.handle.set_alternate_setting(3, 1)?;
+        self.handle.clear_halt(0x83)?;
+        Ok(())
+    }
+
+    fn write(&self, endpoint: u8, data: &[u8], timeout: Duration) -> Result<usize> {
+        Ok(self.handle.write_bulk(endpoint, data, timeout)?)
+    }
+
+    fn read(&self, endpoint: u8, buf: &mut [u8], timeout: Duration) -> Result<usize> {
+        Ok(self.handle.read_bulk(endpoint, buf, timeout)?)
+    }
+}
+
+/// An in-memory [`BeacnTransport`] for tests, which records every request it
+/// receives and replays a queue of canned 8-byte responses.
+#[derive(Default)]
+pub struct MockTransport {
+    requests: std::sync::Mutex<Vec<(u8, Vec<u8>)>>,
+    responses: std::sync::Mutex<std::collections::VecDeque<[u8; 8]>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a canned 8-byte response to be returned by the next `read`.
+    pub fn push_response(&self, response: [u8; 8]) {
+        self.responses.lock().unwrap().push_back(response);
+    }
+
+    /// Returns every request seen so far as `(endpoint, data)` pairs.
+    pub fn requests(&self) -> Vec<(u8, Vec<u8>)> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl BeacnTransport for MockTransport {
+    fn claim(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn write(&self, endpoint: u8, data: &[u8], _timeout: Duration) -> Result<usize> {
+        self.requests
+            .lock()
+            .unwrap()
+            .push((endpoint, data.to_vec()));
+        Ok(data.len())
+    }
+
+    fn read(&self, _endpoint: u8, buf: &mut [u8], _timeout: Duration) -> Result<usize> {
+        let Some(response) = self.responses.lock().unwrap().pop_front() else {
+            bail!("MockTransport has no queued response");
+        };
+        let len = response.len().min(buf.len());
+        buf[..len].copy_from_slice(&response[..len]);
+        Ok(len)
+    }
+}